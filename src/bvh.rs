@@ -0,0 +1,208 @@
+use super::{intersect, Intersect, Primitive, Ray, Vec3};
+
+#[derive(Debug, Clone)]
+pub struct Aabb {
+    min: Vec3,
+    max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Aabb {
+        Aabb { min: min, max: max }
+    }
+
+    pub fn surrounding(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.min(&other.min), self.max.max(&other.max))
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        self.min.add(&self.max).mulf(0.5)
+    }
+
+    // Slab test: for each axis, compute the entry/exit t, shrinking [tmin, tmax] as we go.
+    pub fn hit(&self, ray: &Ray) -> bool {
+        let mut tmin = std::f32::NEG_INFINITY;
+        let mut tmax = std::f32::INFINITY;
+
+        for axis in 0u8..3u8 {
+            let inv_d = 1.0 / ray.direction.axis(axis);
+            let mut t0 = (self.min.axis(axis) - ray.origin.axis(axis)) * inv_d;
+            let mut t1 = (self.max.axis(axis) - ray.origin.axis(axis)) * inv_d;
+
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            tmin = tmin.max(t0);
+            tmax = tmax.min(t1);
+
+            if tmax < tmin {
+                return false;
+            }
+        }
+
+        // tmax < 0 means the whole slab interval lies behind the ray origin.
+        tmax >= 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_hit() {
+        let ray = Ray {
+            origin: Vec3::new(-5.0, 0.0, 0.0),
+            direction: Vec3::new(1.0, 0.0, 0.0),
+        };
+
+        assert!(unit_box().hit(&ray));
+    }
+
+    #[test]
+    fn test_miss() {
+        let ray = Ray {
+            origin: Vec3::new(-5.0, 5.0, 0.0),
+            direction: Vec3::new(1.0, 0.0, 0.0),
+        };
+
+        assert!(!unit_box().hit(&ray));
+    }
+
+    #[test]
+    fn test_behind_origin() {
+        // The box is behind the ray origin, so the ray itself never reaches it
+        // even though the underlying infinite line does.
+        let ray = Ray {
+            origin: Vec3::new(5.0, 0.0, 0.0),
+            direction: Vec3::new(1.0, 0.0, 0.0),
+        };
+
+        assert!(!unit_box().hit(&ray));
+    }
+}
+
+fn primitive_bounds(primitive: &Primitive) -> Aabb {
+    match primitive {
+        Primitive::Sphere(sphere) => {
+            let r = Vec3::new(sphere.radius, sphere.radius, sphere.radius);
+            Aabb::new(sphere.center.sub(&r), sphere.center.add(&r))
+        }
+        Primitive::Triangle(tri) => {
+            let (v0, v1, v2) = tri.vertices();
+            Aabb::new(v0.min(v1).min(v2), v0.max(v1).max(v2))
+        }
+    }
+}
+
+enum BvhNode {
+    Empty,
+    Leaf(usize),
+    Node {
+        bounds: Aabb,
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+impl BvhNode {
+    fn build(primitives: &[Primitive], indices: &mut [usize]) -> BvhNode {
+        if indices.len() == 1 {
+            return BvhNode::Leaf(indices[0]);
+        }
+
+        let bounds = indices
+            .iter()
+            .map(|&i| primitive_bounds(&primitives[i]))
+            .fold(None, |acc: Option<Aabb>, b| match acc {
+                None => Some(b),
+                Some(acc) => Some(acc.surrounding(&b)),
+            })
+            .unwrap();
+
+        let extent = bounds.max.sub(&bounds.min);
+        let axis = if extent.axis(0) > extent.axis(1) && extent.axis(0) > extent.axis(2) {
+            0
+        } else if extent.axis(1) > extent.axis(2) {
+            1
+        } else {
+            2
+        };
+
+        indices.sort_by(|&a, &b| {
+            let ca = primitive_bounds(&primitives[a]).centroid().axis(axis);
+            let cb = primitive_bounds(&primitives[b]).centroid().axis(axis);
+            ca.partial_cmp(&cb).unwrap()
+        });
+
+        let mid = indices.len() / 2;
+        let (left_indices, right_indices) = indices.split_at_mut(mid);
+
+        BvhNode::Node {
+            bounds: bounds,
+            left: Box::new(BvhNode::build(primitives, left_indices)),
+            right: Box::new(BvhNode::build(primitives, right_indices)),
+        }
+    }
+
+    fn intersect<'a>(&self, primitives: &'a [Primitive], ray: &Ray) -> Option<Intersect<'a>> {
+        match self {
+            BvhNode::Empty => None,
+            BvhNode::Leaf(i) => intersect(&primitives[*i], ray),
+            BvhNode::Node { bounds, left, right } => {
+                if !bounds.hit(ray) {
+                    return None;
+                }
+
+                match (
+                    left.intersect(primitives, ray),
+                    right.intersect(primitives, ray),
+                ) {
+                    (None, None) => None,
+                    (Some(l), None) => Some(l),
+                    (None, Some(r)) => Some(r),
+                    (Some(l), Some(r)) => {
+                        if l.t < r.t {
+                            Some(l)
+                        } else {
+                            Some(r)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct Bvh<'a> {
+    primitives: &'a [Primitive],
+    root: BvhNode,
+}
+
+impl<'a> Bvh<'a> {
+    pub fn new(primitives: &'a [Primitive]) -> Bvh<'a> {
+        let mut indices: Vec<usize> = (0..primitives.len()).collect();
+
+        // An empty scene has no bounds to build a tree from; a sentinel
+        // node that always misses keeps intersect() total for that case.
+        let root = if indices.is_empty() {
+            BvhNode::Empty
+        } else {
+            BvhNode::build(primitives, &mut indices)
+        };
+
+        Bvh {
+            primitives: primitives,
+            root: root,
+        }
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Option<Intersect<'a>> {
+        self.root.intersect(self.primitives, ray)
+    }
+}
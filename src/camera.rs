@@ -0,0 +1,67 @@
+use sampling::{sample_unit_disk, Sample2D};
+use super::{Ray, Vec3};
+
+pub struct Camera {
+    origin: Vec3,
+    lower_left_corner: Vec3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    lens_radius: f32,
+}
+
+impl Camera {
+    pub fn new(
+        lookfrom: Vec3,
+        lookat: Vec3,
+        vup: Vec3,
+        vfov: f32,
+        aspect: f32,
+        aperture: f32,
+        focus_dist: f32,
+    ) -> Camera {
+        let half_height = (vfov.to_radians() / 2.0).tan();
+        let half_width = aspect * half_height;
+
+        let w = lookfrom.sub(&lookat).normalize();
+        let u = vup.cross(&w).normalize();
+        let v = w.cross(&u);
+
+        let lower_left_corner = lookfrom
+            .sub(&u.mulf(half_width * focus_dist))
+            .sub(&v.mulf(half_height * focus_dist))
+            .sub(&w.mulf(focus_dist));
+
+        Camera {
+            origin: lookfrom,
+            lower_left_corner: lower_left_corner,
+            horizontal: u.mulf(2.0 * half_width * focus_dist),
+            vertical: v.mulf(2.0 * half_height * focus_dist),
+            u: u,
+            v: v,
+            lens_radius: aperture / 2.0,
+        }
+    }
+
+    pub fn get_ray(&self, s: f32, t: f32, lens_sample: &Sample2D) -> Ray {
+        let (rd_x, rd_y) = sample_unit_disk(lens_sample);
+        let offset = self
+            .u
+            .mulf(rd_x * self.lens_radius)
+            .add(&self.v.mulf(rd_y * self.lens_radius));
+
+        let direction = self
+            .lower_left_corner
+            .add(&self.horizontal.mulf(s))
+            .add(&self.vertical.mulf(t))
+            .sub(&self.origin)
+            .sub(&offset)
+            .normalize();
+
+        Ray {
+            origin: self.origin.add(&offset),
+            direction: direction,
+        }
+    }
+}
@@ -1,4 +1,15 @@
 extern crate rand;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+extern crate serde_json;
+
+mod bvh;
+mod camera;
+mod obj;
+mod sampling;
+mod scene_config;
+mod triangle;
 
 use rand::Rng;
 use std::fs::File;
@@ -12,11 +23,12 @@ pub struct Vec3 {
     z: f32,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Material {
     Diffuse,
     Mirror,
     Glass,
+    Glossy { exponent: f32 },
 }
 
 impl Vec3 {
@@ -45,6 +57,38 @@ impl Vec3 {
         m.x + m.y + m.z
     }
 
+    pub fn cross(&self, other: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn min(&self, other: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    pub fn max(&self, other: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+
+    pub fn axis(&self, i: u8) -> f32 {
+        match i {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
     pub fn length2(&self) -> f32 {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
@@ -73,7 +117,7 @@ impl Ray {
 #[derive(Debug, PartialEq)]
 pub struct Intersect<'a> {
     t: f32,
-    sphere: &'a Sphere,
+    primitive: &'a Primitive,
 }
 
 #[derive(Debug, PartialEq)]
@@ -85,7 +129,36 @@ pub struct Sphere {
     material: Material,
 }
 
-pub fn intersect<'a>(sphere: &'a Sphere, ray: &Ray) -> Option<Intersect<'a>> {
+#[derive(Debug, PartialEq)]
+pub enum Primitive {
+    Sphere(Sphere),
+    Triangle(triangle::Triangle),
+}
+
+impl Primitive {
+    pub fn material(&self) -> &Material {
+        match self {
+            Primitive::Sphere(sphere) => &sphere.material,
+            Primitive::Triangle(tri) => tri.material(),
+        }
+    }
+
+    pub fn color(&self) -> &Vec3 {
+        match self {
+            Primitive::Sphere(sphere) => &sphere.color,
+            Primitive::Triangle(tri) => tri.color(),
+        }
+    }
+
+    pub fn normal_at(&self, p: &Vec3) -> Vec3 {
+        match self {
+            Primitive::Sphere(sphere) => p.sub(&sphere.center).normalize(),
+            Primitive::Triangle(tri) => tri.normal(),
+        }
+    }
+}
+
+fn intersect_sphere(sphere: &Sphere, ray: &Ray) -> Option<f32> {
     let a = ray.direction.length2();
     let b = -2.0 * ray.direction.dot(&sphere.center.sub(&ray.origin));
     let c = (sphere.center.sub(&ray.origin)).length2() - sphere.radius * sphere.radius;
@@ -99,17 +172,11 @@ pub fn intersect<'a>(sphere: &'a Sphere, ray: &Ray) -> Option<Intersect<'a>> {
         let t = (-b - det_sqrt) / (2.0 * a);
 
         if t >= 0. {
-            Some(Intersect {
-                t: t,
-                sphere: sphere,
-            })
+            Some(t)
         } else {
             let t2 = (-b + det_sqrt) / (2.0 * a);
             if t2 >= 0. {
-                Some(Intersect {
-                    t: t2,
-                    sphere: sphere,
-                })
+                Some(t2)
             } else {
                 None
             }
@@ -117,6 +184,15 @@ pub fn intersect<'a>(sphere: &'a Sphere, ray: &Ray) -> Option<Intersect<'a>> {
     }
 }
 
+pub fn intersect<'a>(primitive: &'a Primitive, ray: &Ray) -> Option<Intersect<'a>> {
+    let t = match primitive {
+        Primitive::Sphere(sphere) => intersect_sphere(sphere, ray),
+        Primitive::Triangle(tri) => triangle::intersect_triangle(tri, ray),
+    };
+
+    t.map(|t| Intersect { t, primitive })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,15 +200,15 @@ mod tests {
     #[test]
     pub fn test_basic_ops() {
         assert_eq!(
-            Vec3::new(1.0, 2.0, 3.0).add(Vec3::new(4.0, 5.0, 6.0)),
+            Vec3::new(1.0, 2.0, 3.0).add(&Vec3::new(4.0, 5.0, 6.0)),
             Vec3::new(5.0, 7.0, 9.0)
         );
         assert_eq!(
-            Vec3::new(1.0, 2.0, 3.0).sub(Vec3::new(4.0, 5.0, 6.0)),
+            Vec3::new(1.0, 2.0, 3.0).sub(&Vec3::new(4.0, 5.0, 6.0)),
             Vec3::new(-3.0, -3.0, -3.0)
         );
         assert_eq!(
-            Vec3::new(1.0, 2.0, 3.0).mul(Vec3::new(4.0, 5.0, 6.0)),
+            Vec3::new(1.0, 2.0, 3.0).mul(&Vec3::new(4.0, 5.0, 6.0)),
             Vec3::new(4.0, 10.0, 18.0)
         );
         assert_eq!(
@@ -143,7 +219,7 @@ mod tests {
 
     #[test]
     pub fn test_dot() {
-        assert_eq!(Vec3::new(1.0, 2.0, 3.0).dot(Vec3::new(4.0, 5.0, 6.0)), 32.0);
+        assert_eq!(Vec3::new(1.0, 2.0, 3.0).dot(&Vec3::new(4.0, 5.0, 6.0)), 32.0);
     }
 
     #[test]
@@ -151,18 +227,24 @@ mod tests {
         assert_eq!(Vec3::new(3.0, 2.0, 1.0).length2(), 14.0)
     }
 
+    fn test_sphere() -> Sphere {
+        Sphere {
+            radius: 3.0,
+            center: Vec3::new(10.0, 0.0, 0.0),
+            emission: Vec3::new(0.0, 0.0, 0.0),
+            color: Vec3::new(0.0, 0.0, 0.0),
+            material: Material::Diffuse,
+        }
+    }
+
     #[test]
     pub fn test_sphere_it_front() {
         let r = Ray {
             origin: Vec3::new(2.0, 0.0, 0.0),
             direction: Vec3::new(1.0, 0.0, 0.0),
         };
-        let sphere = Sphere {
-            center: Vec3::new(10.0, 0.0, 0.0),
-            radius: 3.0,
-        };
 
-        assert_eq!(intersect(&sphere, &r), Some(Intersect::new(5.0)));
+        assert_eq!(intersect_sphere(&test_sphere(), &r), Some(5.0));
     }
 
     #[test]
@@ -171,12 +253,8 @@ mod tests {
             origin: Vec3::new(10.0, 0.0, 0.0),
             direction: Vec3::new(1.0, 0.0, 0.0),
         };
-        let sphere = Sphere {
-            center: Vec3::new(10.0, 0.0, 0.0),
-            radius: 3.0,
-        };
 
-        assert_eq!(intersect(&sphere, &r), Some(Intersect::new(3.0)));
+        assert_eq!(intersect_sphere(&test_sphere(), &r), Some(3.0));
     }
 
     #[test]
@@ -185,39 +263,9 @@ mod tests {
             origin: Vec3::new(15.0, 0.0, 0.0),
             direction: Vec3::new(1.0, 0.0, 0.0),
         };
-        let sphere = Sphere {
-            center: Vec3::new(10.0, 0.0, 0.0),
-            radius: 3.0,
-        };
-
-        assert_eq!(intersect(&sphere, &r), None);
-    }
-}
 
-pub fn intersect_scene<'a>(scene: &'a Scene, ray: &Ray) -> Option<Intersect<'a>> {
-    let mut res = None;
-    for sphere in &scene.spheres {
-        let new_it = intersect(&sphere, ray);
-
-        res = match new_it {
-            None => res,
-            Some(Intersect { t, sphere: _sphere }) => match res {
-                None => new_it,
-                Some(Intersect {
-                    t: t2,
-                    sphere: _sphere2,
-                }) => {
-                    if t < t2 {
-                        new_it
-                    } else {
-                        res
-                    }
-                }
-            },
-        }
+        assert_eq!(intersect_sphere(&test_sphere(), &r), None);
     }
-
-    res
 }
 
 pub struct Light {
@@ -226,113 +274,209 @@ pub struct Light {
 }
 
 pub struct Scene {
-    spheres: Vec<Sphere>,
+    primitives: Vec<Primitive>,
     lights: Vec<Light>,
 }
 
 impl Scene {
-    pub fn new(spheres: Vec<Sphere>, lights: Vec<Light>) -> Scene {
+    pub fn new(primitives: Vec<Primitive>, lights: Vec<Light>) -> Scene {
         Scene {
-            spheres: spheres,
+            primitives: primitives,
             lights: lights,
         }
     }
 }
 
-pub fn compute_indirect_lighting(scene: &Scene, sphere: &Sphere, p: &Vec3, depth: u32) -> Vec3 {
-    let mut rng = rand::thread_rng();
-    let normal_surface_norm = p.sub(&sphere.center).normalize();
-    // That's not how you generate an uniform direction...
-    let new_direction = Vec3::new(rng.gen(), rng.gen(), rng.gen())
-        .mulf(2.0)
-        .sub(&Vec3::new(1.0, 1.0, 1.0))
-        .normalize();
-
-    // rejection sampling, that's not how you are supposed to do that!
-    let dot = normal_surface_norm.dot(&new_direction);
-
-    // TODO; compute same side, not crappy dot
-    if dot > 0.0 {
-        compute_indirect_lighting(scene, sphere, p, depth)
-    } else {
-        let r = Ray {
-            origin: p.add(&new_direction.mulf(0.01)),
-            direction: new_direction,
-        };
+pub fn compute_indirect_lighting(
+    scene: &Scene,
+    bvh: &bvh::Bvh,
+    primitive: &Primitive,
+    p: &Vec3,
+    depth: u32,
+    max_depth: u32,
+    throughput: &Vec3,
+) -> Vec3 {
+    let normal = primitive.normal_at(p);
+    let (tangent, bitangent) = sampling::branchless_onb(&normal);
+    let s = sampling::sample_cosinus_hemisphere(&sampling::thread_sample_2d());
+
+    let world_direction = tangent
+        .mulf(s.value.x)
+        .add(&bitangent.mulf(s.value.y))
+        .add(&normal.mulf(s.value.z));
 
-        sphere
-            .color
-            .mulf(dot.abs() * 2.0)
-            .mul(&radiance(scene, &r, depth + 1))
-    }
+    let r = Ray {
+        origin: p.add(&world_direction.mulf(0.01)),
+        direction: world_direction,
+    };
+
+    let new_throughput = throughput.mul(primitive.color());
+
+    // The cos(theta)/PI pdf cancels the cosine term of the rendering equation,
+    // so the estimator reduces to a plain product with no weighting factor.
+    primitive
+        .color()
+        .mul(&radiance(scene, bvh, &r, depth + 1, max_depth, &new_throughput))
 }
 
-pub fn compute_direct_lighting(scene: &Scene, sphere: &Sphere, light: &Light, p: &Vec3) -> Vec3 {
+pub fn compute_direct_lighting(
+    bvh: &bvh::Bvh,
+    primitive: &Primitive,
+    light: &Light,
+    p: &Vec3,
+) -> Vec3 {
     let light_p = &light.position;
     let sphere_to_light = light_p.sub(&p);
     let d2 = sphere_to_light.length2();
     let d = d2.sqrt();
     let sphere_to_light_norm = sphere_to_light.mulf(1.0 / d);
-    let normal_surface_norm = p.sub(&sphere.center).normalize();
+    let normal_surface_norm = primitive.normal_at(p);
     let abs_dot = normal_surface_norm.dot(&sphere_to_light_norm).abs();
     let r = Ray {
         origin: p.add(&sphere_to_light_norm.mulf(0.01)),
         direction: sphere_to_light_norm,
     };
-    let it = intersect_scene(scene, &r);
+    let it = bvh.intersect(&r);
 
     let occludded = match it {
         None => false,
-        Some(Intersect { t, sphere: _sphere }) => t < d,
+        Some(Intersect { t, primitive: _ }) => t < d,
     };
 
     if occludded {
         Vec3::new(0.0, 0.0, 0.0)
     } else {
-        sphere
-            .color
+        primitive
+            .color()
             .mulf(abs_dot / (3.14159 * d2))
             .mul(&light.emission)
     }
 }
 
-pub fn radiance(scene: &Scene, ray: &Ray, depth: u32) -> Vec3 {
-    if depth > 3 {
+pub fn radiance(
+    scene: &Scene,
+    bvh: &bvh::Bvh,
+    ray: &Ray,
+    depth: u32,
+    max_depth: u32,
+    throughput: &Vec3,
+) -> Vec3 {
+    if depth > max_depth {
+        // Safety cap only: with max_depth set well above the Russian roulette
+        // threshold below, paths are expected to already be terminated by RR
+        // long before they ever reach this hard cutoff.
         Vec3::new(0.0, 0.0, 0.0)
     } else {
-        let it = intersect_scene(&scene, ray);
+        let it = bvh.intersect(ray);
 
         match it {
             None => Vec3::new(0.0, 0.0, 0.0), // black if no it
-            Some(Intersect { t, sphere }) => {
+            Some(Intersect { t, primitive }) => {
+                // Russian roulette: past a few bounces, kill low-throughput paths
+                // with probability 1-p_survive and reweight survivors by 1/p_survive,
+                // so the estimator stays unbiased without a hard energy cutoff.
+                let rr_boost = if depth >= 4 {
+                    let p_survive = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                    let mut rng = rand::thread_rng();
+
+                    if rng.gen::<f32>() > p_survive {
+                        return Vec3::new(0.0, 0.0, 0.0);
+                    }
+
+                    1.0 / p_survive
+                } else {
+                    1.0
+                };
+
                 let p = ray.get_p(t);
-                match sphere.material {
+                let result = match primitive.material() {
                     Material::Diffuse => {
                         // There is only one light, that's easier
-                        compute_direct_lighting(&scene, &sphere, &scene.lights[0], &p)
-                            .add(&compute_indirect_lighting(&scene, &sphere, &p, depth))
+                        compute_direct_lighting(bvh, &primitive, &scene.lights[0], &p).add(
+                            &compute_indirect_lighting(
+                                &scene, bvh, &primitive, &p, depth, max_depth, throughput,
+                            ),
+                        )
                     }
                     Material::Mirror => {
-                        let normal = p.sub(&sphere.center).normalize();
+                        let normal = primitive.normal_at(&p);
                         let dir = reflect(&ray.direction, &normal);
                         let r = Ray {
                             origin: p.add(&dir.mulf(0.01)),
                             direction: dir,
                         };
 
-                        radiance(scene, &r, depth + 1)
+                        radiance(scene, bvh, &r, depth + 1, max_depth, throughput)
                     }
                     Material::Glass => {
-                        let normal = p.sub(&sphere.center).normalize();
-                        let dir = reflect(&ray.direction, &normal);
+                        let normal = primitive.normal_at(&p);
+                        let entering = ray.direction.dot(&normal) < 0.0;
+                        let (n1, n2) = if entering { (1.0, 1.5) } else { (1.5, 1.0) };
+                        // normal facing against the incident ray, as required by the refract formula
+                        let n = if entering {
+                            normal.clone()
+                        } else {
+                            normal.mulf(-1.0)
+                        };
+                        let eta = n1 / n2;
+                        let cos_i = -ray.direction.dot(&n);
+                        let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
+
+                        let dir = if k < 0.0 {
+                            // Total internal reflection
+                            reflect(&ray.direction, &normal)
+                        } else {
+                            let refracted = ray.direction.mulf(eta).add(&n.mulf(eta * cos_i - k.sqrt()));
+
+                            let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+                            let reflectance = r0 + (1.0 - r0) * (1.0 - cos_i).powi(5);
+
+                            let mut rng = rand::thread_rng();
+                            if rng.gen::<f32>() < reflectance {
+                                reflect(&ray.direction, &normal)
+                            } else {
+                                refracted
+                            }
+                        };
+
                         let r = Ray {
                             origin: p.add(&dir.mulf(0.01)),
                             direction: dir,
                         };
 
-                        radiance(scene, &r, depth + 1)
+                        radiance(scene, bvh, &r, depth + 1, max_depth, throughput)
                     }
-                }
+                    Material::Glossy { exponent } => {
+                        let normal = primitive.normal_at(&p);
+                        let ideal = reflect(&ray.direction, &normal);
+                        let (tangent, bitangent) = sampling::branchless_onb(&ideal);
+
+                        // Resample until the perturbed lobe direction stays above the surface.
+                        let dir = loop {
+                            let lobe =
+                                sampling::sample_phong_lobe(&sampling::thread_sample_2d(), *exponent);
+                            let candidate = tangent
+                                .mulf(lobe.value.x)
+                                .add(&bitangent.mulf(lobe.value.y))
+                                .add(&ideal.mulf(lobe.value.z));
+
+                            if candidate.dot(&normal) > 0.0 {
+                                break candidate;
+                            }
+                        };
+
+                        let r = Ray {
+                            origin: p.add(&dir.mulf(0.01)),
+                            direction: dir,
+                        };
+
+                        primitive
+                            .color()
+                            .mul(&radiance(scene, bvh, &r, depth + 1, max_depth, throughput))
+                    }
+                };
+
+                result.mulf(rr_boost)
             }
         }
     }
@@ -396,102 +540,46 @@ impl Image {
 }
 
 pub fn main() {
-    let scene = Scene::new(
-        vec![
-            Sphere {
-                radius: 1000.0,
-                center: Vec3::new(1000.0 + 1.0, 40.8, 81.6),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: (Vec3::new(0.75, 0.25, 0.25)),
-                material: Material::Diffuse,
-            }, // Left
-            Sphere {
-                radius: 1000.0,
-                center: Vec3::new(-1000.0 + 99.0, 40.8, 81.6),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: Vec3::new(0.25, 0.25, 0.75),
-                material: Material::Diffuse,
-            }, // Right
-            Sphere {
-                radius: 1000.0,
-                center: Vec3::new(50.0, 40.8, 1000.0),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: Vec3::new(0.75, 0.75, 0.75),
-                material: Material::Diffuse,
-            }, // Back
-            Sphere {
-                radius: 1000.0,
-                center: Vec3::new(50.0, 40.8, -1000.0 + 170.0),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: Vec3::new(0.0, 0.0, 0.0),
-                material: Material::Diffuse,
-            }, // Front
-            Sphere {
-                radius: 1000.0,
-                center: Vec3::new(50.0, 1000.0, 81.6),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: Vec3::new(0.75, 0.75, 0.75),
-                material: Material::Diffuse,
-            }, // Bottom
-            Sphere {
-                radius: 1000.0,
-                center: Vec3::new(50.0, -1000.0 + 81.6, 81.6),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: Vec3::new(0.75, 0.75, 0.75),
-                material: Material::Diffuse,
-            }, // Top
-            Sphere {
-                radius: 16.5,
-                center: Vec3::new(27.0, 16.5, 47.0),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: Vec3::new(0.99, 0.0, 0.99),
-                material: Material::Mirror,
-            }, // Mirror
-            Sphere {
-                radius: 16.5,
-                center: Vec3::new(73.0, 16.5, 78.0),
-                emission: Vec3::new(0.0, 0.0, 0.0),
-                color: Vec3::new(0.0, 0.99, 0.99),
-                material: Material::Glass,
-            }, // Glass
-
-               //,Sphere {radius: 1000.0  ,center:(Vec3::new(50.0, (81.6-16.5), 81.6)),emission: ((Vec3::new(400.0, 400.0, 400.0)))   ,color:Vec3::new(0.0,0.0,0.0),material:  Material::Diffuse } // Light
-        ],
-        vec![Light {
-            emission: Vec3::new(5000.0, 5000.0, 5000.0),
-            position: Vec3::new(50.0, 81.6 - 16.4, 81.6),
-        }],
-    );
-
-    let w = 768;
-    let h = 768;
-
-    let mut im = Image::new(w, h, Vec3::new(0., 0., 0.));
-
-    for y in 0..h {
-        for x in 0..w {
-            let raster_x = 100. * ((x as f32) / (w as f32) - 0.5);
-            let raster_x2 = 1.3 * raster_x;
-            let raster_y = 100. * (((h - y) as f32) / (h as f32) - 0.5);
-            let raster_y2 = 1.3 * raster_y;
-
-            let p0 = Vec3::new(raster_x, raster_y, 150.0);
-            let p1 = Vec3::new(raster_x2, raster_y2, 0.0);
-            let direction = (p1.sub(&p0)).normalize();
-
-            let ray = Ray {
-                origin: p0.add(&Vec3::new(50.0, 40.0, 0.0)),
-                direction: direction,
-            };
+    let filepath = std::env::args()
+        .nth(1)
+        .expect("usage: rust_rt <scene.json>");
+
+    let (scene, cam, render) = scene_config::load(&filepath);
+
+    let scene_bvh = bvh::Bvh::new(&scene.primitives);
+
+    let mut im = Image::new(render.width, render.height, Vec3::new(0., 0., 0.));
+
+    let mut rng = rand::thread_rng();
 
+    for y in 0..render.height {
+        for x in 0..render.width {
             let mut color_accum = Vec3::new(0.0, 0.0, 0.0);
 
-            for _sample in 0..10 {
-                let color = radiance(&scene, &ray, 0);
+            for _sample in 0..render.samples_per_pixel {
+                // Jitter the raster position within the pixel for anti-aliasing.
+                let jx = (x as f32) + (rng.gen::<f32>() - 0.5);
+                let jy = (y as f32) + (rng.gen::<f32>() - 0.5);
+                let s = jx / (render.width as f32);
+                let t = ((render.height as f32) - jy) / (render.height as f32);
+                let ray = cam.get_ray(s, t, &sampling::thread_sample_2d());
+
+                let color = radiance(
+                    &scene,
+                    &scene_bvh,
+                    &ray,
+                    0,
+                    render.max_depth,
+                    &Vec3::new(1.0, 1.0, 1.0),
+                );
                 color_accum = color_accum.add(&color);
             }
 
-            im.set_pixel(x, y, color_accum.mulf(1.0 / 11.0));
+            im.set_pixel(
+                x,
+                y,
+                color_accum.mulf(1.0 / (render.samples_per_pixel as f32)),
+            );
         }
     }
 
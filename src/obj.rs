@@ -0,0 +1,58 @@
+use std::fs;
+
+use super::{Material, Vec3};
+use triangle::Triangle;
+
+fn parse_face_index(token: &str, vertex_count: usize) -> usize {
+    // OBJ face tokens can be "v", "v/vt" or "v/vt/vn"; we only need the vertex index.
+    // The index itself may be a positive 1-based index, or a negative index
+    // counting back from the last vertex parsed so far (e.g. -1 is that vertex).
+    let index: i64 = token.split('/').next().unwrap().parse().unwrap();
+
+    if index < 0 {
+        (vertex_count as i64 + index) as usize
+    } else {
+        (index - 1) as usize
+    }
+}
+
+// Parses the `v` and `f` lines of a Wavefront .obj file into triangles sharing
+// a single material, triangulating any face with more than three vertices as a fan.
+pub fn load(filepath: &str, color: Vec3, emission: Vec3, material: Material) -> Vec<Triangle> {
+    let contents = fs::read_to_string(filepath).unwrap();
+
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::new();
+
+    for line in contents.lines() {
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                let x: f32 = tokens.next().unwrap().parse().unwrap();
+                let y: f32 = tokens.next().unwrap().parse().unwrap();
+                let z: f32 = tokens.next().unwrap().parse().unwrap();
+                vertices.push(Vec3::new(x, y, z));
+            }
+            Some("f") => {
+                let indices: Vec<usize> = tokens
+                    .map(|token| parse_face_index(token, vertices.len()))
+                    .collect();
+
+                for i in 1..indices.len() - 1 {
+                    triangles.push(Triangle::new(
+                        vertices[indices[0]].clone(),
+                        vertices[indices[i]].clone(),
+                        vertices[indices[i + 1]].clone(),
+                        emission.clone(),
+                        color.clone(),
+                        material,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    triangles
+}
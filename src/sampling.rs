@@ -1,7 +1,7 @@
 extern crate rand;
 
 use rand::Rng;
-use vec3::Vec3;
+use super::Vec3;
 
 pub struct Sample2D
 {
@@ -35,6 +35,14 @@ pub fn sample_cosinus_hemisphere(Sample2D{u, v}: &Sample2D) -> Sample<Vec3>
 		     sqrt_v)}
 }
 
+// Formula 52: sampling a point on a unit disk, used for lens/aperture sampling
+pub fn sample_unit_disk(Sample2D{u, v}: &Sample2D) -> (f32, f32)
+{
+    let r = u.sqrt();
+    let theta = 2.0 * std::f32::consts::PI * v;
+    (r * theta.cos(), r * theta.sin())
+}
+
 // Basis rotation, based on: http://jcgt.org/published/0006/01/01/ Building an Orthonormal Basis, Revisited
 pub fn branchless_onb(n : &Vec3) -> (Vec3, Vec3)
 {
@@ -47,6 +55,19 @@ pub fn branchless_onb(n : &Vec3) -> (Vec3, Vec3)
      )
 }
 
+// Formula 34: sampling a cosine-power lobe around the local z axis, used to
+// perturb an ideal reflection direction for glossy/Phong-lobe materials.
+pub fn sample_phong_lobe(Sample2D{u, v}: &Sample2D, exponent: f32) -> Sample<Vec3>
+{
+    let cos_alpha = v.powf(1.0 / (exponent + 1.0));
+    let sin_alpha = (1.0 - cos_alpha * cos_alpha).sqrt();
+    let phi = 2.0 * std::f32::consts::PI * u;
+    Sample{pdf: (exponent + 1.0) / (2.0 * std::f32::consts::PI) * cos_alpha.powf(exponent),
+	   value: Vec3::new(sin_alpha * phi.cos(),
+		     sin_alpha * phi.sin(),
+		     cos_alpha)}
+}
+
 pub fn flip_normal(a : &Vec3, n : &Vec3) -> Vec3
 {
     if n.dot(a) > 0.0
@@ -0,0 +1,157 @@
+use std::fs;
+
+use camera::Camera;
+use obj;
+use super::{Light, Material, Primitive, Scene, Sphere, Vec3};
+
+#[derive(Deserialize)]
+struct Vec3Config {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl Vec3Config {
+    fn to_vec3(&self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+}
+
+#[derive(Deserialize)]
+struct SphereConfig {
+    radius: f32,
+    center: Vec3Config,
+    emission: Vec3Config,
+    color: Vec3Config,
+    material: String,
+    #[serde(default)]
+    exponent: f32,
+}
+
+#[derive(Deserialize)]
+struct MeshConfig {
+    obj_path: String,
+    emission: Vec3Config,
+    color: Vec3Config,
+    material: String,
+    #[serde(default)]
+    exponent: f32,
+}
+
+#[derive(Deserialize)]
+struct LightConfig {
+    position: Vec3Config,
+    emission: Vec3Config,
+}
+
+#[derive(Deserialize)]
+struct CameraConfig {
+    position: Vec3Config,
+    look_at: Vec3Config,
+    up: Vec3Config,
+    fov: f32,
+    #[serde(default)]
+    aperture: f32,
+    #[serde(default = "default_focus_distance")]
+    focus_distance: f32,
+}
+
+fn default_focus_distance() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct RenderConfig {
+    width: i32,
+    height: i32,
+    max_depth: u32,
+    samples_per_pixel: u32,
+}
+
+#[derive(Deserialize)]
+struct SceneConfig {
+    spheres: Vec<SphereConfig>,
+    #[serde(default)]
+    meshes: Vec<MeshConfig>,
+    lights: Vec<LightConfig>,
+    camera: CameraConfig,
+    render: RenderConfig,
+}
+
+pub struct RenderSettings {
+    pub width: i32,
+    pub height: i32,
+    pub max_depth: u32,
+    pub samples_per_pixel: u32,
+}
+
+fn parse_material(name: &str, exponent: f32) -> Material {
+    match name {
+        "mirror" => Material::Mirror,
+        "glass" => Material::Glass,
+        "glossy" => Material::Glossy { exponent: exponent },
+        _ => Material::Diffuse,
+    }
+}
+
+// Deserializes a scene description and builds the Scene/Camera/RenderSettings
+// used to drive a render, so changing a render doesn't require recompiling.
+pub fn load(filepath: &str) -> (Scene, Camera, RenderSettings) {
+    let contents = fs::read_to_string(filepath).unwrap();
+    let config: SceneConfig = serde_json::from_str(&contents).unwrap();
+
+    let mut primitives: Vec<Primitive> = config
+        .spheres
+        .into_iter()
+        .map(|s| {
+            Primitive::Sphere(Sphere {
+                radius: s.radius,
+                center: s.center.to_vec3(),
+                emission: s.emission.to_vec3(),
+                color: s.color.to_vec3(),
+                material: parse_material(&s.material, s.exponent),
+            })
+        })
+        .collect();
+
+    for m in config.meshes {
+        let triangles = obj::load(
+            &m.obj_path,
+            m.color.to_vec3(),
+            m.emission.to_vec3(),
+            parse_material(&m.material, m.exponent),
+        );
+        primitives.extend(triangles.into_iter().map(Primitive::Triangle));
+    }
+
+    let lights = config
+        .lights
+        .into_iter()
+        .map(|l| Light {
+            position: l.position.to_vec3(),
+            emission: l.emission.to_vec3(),
+        })
+        .collect();
+
+    let scene = Scene::new(primitives, lights);
+
+    let aspect = (config.render.width as f32) / (config.render.height as f32);
+    let camera = Camera::new(
+        config.camera.position.to_vec3(),
+        config.camera.look_at.to_vec3(),
+        config.camera.up.to_vec3(),
+        config.camera.fov,
+        aspect,
+        config.camera.aperture,
+        config.camera.focus_distance,
+    );
+
+    let render = RenderSettings {
+        width: config.render.width,
+        height: config.render.height,
+        max_depth: config.render.max_depth,
+        samples_per_pixel: config.render.samples_per_pixel,
+    };
+
+    (scene, camera, render)
+}
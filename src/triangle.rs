@@ -0,0 +1,126 @@
+use super::{Material, Ray, Vec3};
+
+#[derive(Debug, PartialEq)]
+pub struct Triangle {
+    v0: Vec3,
+    v1: Vec3,
+    v2: Vec3,
+    emission: Vec3,
+    color: Vec3,
+    material: Material,
+}
+
+impl Triangle {
+    pub fn new(v0: Vec3, v1: Vec3, v2: Vec3, emission: Vec3, color: Vec3, material: Material) -> Triangle {
+        Triangle {
+            v0: v0,
+            v1: v1,
+            v2: v2,
+            emission: emission,
+            color: color,
+            material: material,
+        }
+    }
+
+    pub fn vertices(&self) -> (&Vec3, &Vec3, &Vec3) {
+        (&self.v0, &self.v1, &self.v2)
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn color(&self) -> &Vec3 {
+        &self.color
+    }
+
+    // The geometric normal, constant over the whole (flat) triangle.
+    pub fn normal(&self) -> Vec3 {
+        let e1 = self.v1.sub(&self.v0);
+        let e2 = self.v2.sub(&self.v0);
+        e1.cross(&e2).normalize()
+    }
+}
+
+// Moller-Trumbore ray/triangle intersection.
+pub fn intersect_triangle(triangle: &Triangle, ray: &Ray) -> Option<f32> {
+    let e1 = triangle.v1.sub(&triangle.v0);
+    let e2 = triangle.v2.sub(&triangle.v0);
+
+    let pvec = ray.direction.cross(&e2);
+    let det = e1.dot(&pvec);
+
+    if det.abs() < 1e-6 {
+        // Ray parallel to the triangle's plane.
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let tvec = ray.origin.sub(&triangle.v0);
+    let u = tvec.dot(&pvec) * inv_det;
+
+    if u < 0.0 || u > 1.0 {
+        return None;
+    }
+
+    let qvec = tvec.cross(&e1);
+    let v = ray.direction.dot(&qvec) * inv_det;
+
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = e2.dot(&qvec) * inv_det;
+
+    if t >= 0.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_triangle() -> Triangle {
+        Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Material::Diffuse,
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let ray = Ray {
+            origin: Vec3::new(0.2, 0.2, -5.0),
+            direction: Vec3::new(0.0, 0.0, 1.0),
+        };
+
+        assert_eq!(intersect_triangle(&test_triangle(), &ray), Some(5.0));
+    }
+
+    #[test]
+    fn test_miss_outside_edge() {
+        let ray = Ray {
+            origin: Vec3::new(2.0, 2.0, -5.0),
+            direction: Vec3::new(0.0, 0.0, 1.0),
+        };
+
+        assert_eq!(intersect_triangle(&test_triangle(), &ray), None);
+    }
+
+    #[test]
+    fn test_parallel_ray() {
+        let ray = Ray {
+            origin: Vec3::new(0.2, 0.2, -5.0),
+            direction: Vec3::new(1.0, 0.0, 0.0),
+        };
+
+        assert_eq!(intersect_triangle(&test_triangle(), &ray), None);
+    }
+}